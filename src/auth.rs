@@ -0,0 +1,335 @@
+//! Finding a github token outside of the command line flags
+
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt, path::PathBuf, str::FromStr};
+
+/// A github token, `Debug` and `Display` print `***` so it can't end up
+/// in logs or error messages
+#[derive(Clone, PartialEq, Eq)]
+pub struct Token(String);
+
+impl Token {
+    pub fn new(token: &str) -> Self {
+        Self(token.trim().to_string())
+    }
+
+    /// The actual token, only for sending it to github or storing it
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+impl FromStr for Token {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
+
+/// An entry in the gh cli's hosts.yml
+#[derive(Deserialize, Debug)]
+struct GhHost {
+    oauth_token: Option<String>,
+    user: Option<String>,
+    /// Newer versions of gh nest the token under each logged in user
+    #[serde(default)]
+    users: BTreeMap<String, Option<GhUser>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GhUser {
+    oauth_token: Option<String>,
+}
+
+/// The directory the gh cli keeps its config in
+fn gh_config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("GH_CONFIG_DIR") {
+        return Some(dir.into());
+    }
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("gh"));
+    }
+    if cfg!(windows) {
+        if let Some(dir) = std::env::var_os("AppData") {
+            return Some(PathBuf::from(dir).join("GitHub CLI"));
+        }
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("gh"))
+}
+
+/// The host gh uses for the api at `base_url`, `api.github.com` is stored
+/// as `github.com` and enterprise servers by their own host
+pub fn gh_host(base_url: &str) -> String {
+    let host = reqwest::Url::parse(base_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    if host == "api.github.com" {
+        "github.com".to_string()
+    } else {
+        host
+    }
+}
+
+/// Read the token for `host` from the gh cli's hosts.yml, the error
+/// describes what was tried
+pub fn gh_cli_token(host: &str) -> Result<Token, String> {
+    let path = gh_config_dir()
+        .ok_or_else(|| "Unable to find the gh config directory".to_string())?
+        .join("hosts.yml");
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+    let hosts: BTreeMap<String, GhHost> = serde_yaml::from_str(&contents)
+        .map_err(|e| format!("Unable to parse {}: {}", path.display(), e))?;
+    let entry = hosts
+        .get(host)
+        .ok_or_else(|| format!("No entry for {} in {}", host, path.display()))?;
+    let token = entry.oauth_token.clone().or_else(|| {
+        let user = entry.user.as_ref()?;
+        entry.users.get(user)?.as_ref()?.oauth_token.clone()
+    });
+    token
+        .map(|t| Token::new(&t))
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| {
+            format!(
+                "No token for {} in {}, gh may be storing it in the system keyring",
+                host,
+                path.display()
+            )
+        })
+}
+
+/// The keyring service tokens are stored under, keyed by username
+const KEYRING_SERVICE: &str = "dependabot-approve";
+
+fn keyring_entry(username: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, username).map_err(|e| keyring_error(username, e))
+}
+
+/// Turn keyring failures into something the user can act on
+fn keyring_error(username: &str, e: keyring::Error) -> String {
+    match e {
+        keyring::Error::NoEntry => format!(
+            "No token stored in the keyring for {0}, run `dependabot-approve login -u {0}` first",
+            username
+        ),
+        keyring::Error::NoStorageAccess(e) | keyring::Error::PlatformFailure(e) => format!(
+            "Unable to access the system keyring, make sure it is available and unlocked: {}",
+            e
+        ),
+        e => format!("Keyring error for {}: {}", username, e),
+    }
+}
+
+/// Read the token `login` stored for `username`
+pub fn keyring_token(username: &str) -> Result<Token, String> {
+    keyring_entry(username)?
+        .get_password()
+        .map(|t| Token::new(&t))
+        .map_err(|e| keyring_error(username, e))
+}
+
+pub fn store_keyring_token(username: &str, token: &Token) -> Result<(), String> {
+    keyring_entry(username)?
+        .set_password(token.expose())
+        .map_err(|e| keyring_error(username, e))
+}
+
+pub fn delete_keyring_token(username: &str) -> Result<(), String> {
+    keyring_entry(username)?
+        .delete_credential()
+        .map_err(|e| keyring_error(username, e))
+}
+
+/// The credentials of a GitHub App installation, used to mint installation
+/// access tokens
+pub struct GithubApp {
+    /// Built with the --proxy and --ca-cert of the run
+    client: reqwest::Client,
+    base_url: String,
+    app_id: u64,
+    installation_id: u64,
+    key: jsonwebtoken::EncodingKey,
+}
+
+#[derive(serde::Serialize)]
+struct AppClaims {
+    iat: i64,
+    exp: i64,
+    iss: String,
+}
+
+#[derive(Deserialize)]
+struct InstallationToken {
+    token: String,
+}
+
+impl GithubApp {
+    pub fn load(
+        app_id: u64,
+        private_key_path: &str,
+        installation_id: u64,
+        base_url: &str,
+        client: reqwest::Client,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let pem = std::fs::read(private_key_path)
+            .map_err(|e| format!("Unable to read {}: {}", private_key_path, e))?;
+        let key = jsonwebtoken::EncodingKey::from_rsa_pem(&pem)
+            .map_err(|e| format!("Invalid private key {}: {}", private_key_path, e))?;
+        Ok(Self {
+            client,
+            base_url: base_url.to_string(),
+            app_id,
+            installation_id,
+            key,
+        })
+    }
+
+    /// A short lived JWT identifying the app itself
+    fn jwt(&self) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        let claims = AppClaims {
+            // allow for some clock drift between us and github
+            iat: now - 60,
+            exp: now + 9 * 60,
+            iss: self.app_id.to_string(),
+        };
+        jsonwebtoken::encode(
+            &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+            &claims,
+            &self.key,
+        )
+    }
+
+    /// Exchange the app's JWT for an installation access token, these
+    /// expire after an hour
+    pub async fn installation_token(&self) -> Result<Token, Box<dyn std::error::Error>> {
+        let url = format!(
+            "{}/app/installations/{}/access_tokens",
+            self.base_url, self.installation_id
+        );
+        log::debug!("getting an installation token from {}", url);
+        let res = self
+            .client
+            .post(&url)
+            .bearer_auth(self.jwt()?)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header(reqwest::header::USER_AGENT, "dependabot-approve")
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            return Err(format!(
+                "Failed to get an installation token for app {}: {}",
+                self.app_id,
+                res.status()
+            )
+            .into());
+        }
+        let token: InstallationToken = serde_json::from_str(&res.text().await?)?;
+        Ok(Token::new(&token.token))
+    }
+}
+
+#[derive(Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+#[derive(Deserialize)]
+struct DeviceTokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+    /// Sent along with `slow_down`
+    interval: Option<u64>,
+}
+
+/// Log in with github's OAuth device flow, `web_base` is the web (not
+/// api) url of the github instance
+pub async fn device_flow(
+    c: &reqwest::Client,
+    web_base: &str,
+    client_id: &str,
+) -> Result<Token, Box<dyn std::error::Error>> {
+    let post = |url: String, body: serde_json::Value| {
+        c.post(url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(reqwest::header::USER_AGENT, "dependabot-approve")
+            .body(body.to_string())
+            .send()
+    };
+    let res = post(
+        format!("{}/login/device/code", web_base),
+        serde_json::json!({ "client_id": client_id, "scope": "repo notifications" }),
+    )
+    .await?;
+    if !res.status().is_success() {
+        return Err(format!("Failed to start the device flow: {}", res.status()).into());
+    }
+    let code: DeviceCode = serde_json::from_str(&res.text().await?)?;
+    println!(
+        "Open {} and enter the code {}",
+        code.verification_uri, code.user_code
+    );
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(code.expires_in);
+    let mut interval = code.interval;
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+        if std::time::Instant::now() >= deadline {
+            return Err("The device code expired before it was entered, please try again".into());
+        }
+        let res = post(
+            format!("{}/login/oauth/access_token", web_base),
+            serde_json::json!({
+                "client_id": client_id,
+                "device_code": code.device_code,
+                "grant_type": "urn:ietf:params:oauth:grant-type:device_code",
+            }),
+        )
+        .await?;
+        let res: DeviceTokenResponse = serde_json::from_str(&res.text().await?)?;
+        if let Some(token) = res.access_token {
+            return Ok(Token::new(&token));
+        }
+        match res.error.as_deref() {
+            Some("authorization_pending") => {}
+            Some("slow_down") => interval = res.interval.unwrap_or(interval + 5),
+            Some("expired_token") => {
+                return Err("The device code expired before it was entered, please try again".into())
+            }
+            Some("access_denied") => return Err("The authorization request was denied".into()),
+            Some(other) => {
+                return Err(format!(
+                    "Device flow failed ({}): {}",
+                    other,
+                    res.error_description.unwrap_or_default()
+                )
+                .into())
+            }
+            None => return Err("Unexpected response from the token endpoint".into()),
+        }
+    }
+}
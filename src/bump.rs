@@ -0,0 +1,488 @@
+use std::fmt;
+
+/// A single dependency bump as described by a dependabot or renovate PR title
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bump {
+    pub dependency: String,
+    /// Renovate titles don't include the version being replaced
+    pub from: Option<String>,
+    pub to: String,
+    /// The manifest directory from a dependabot title ending in `in /path`
+    pub directory: Option<String>,
+}
+
+/// A dependabot title with its versions parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyBump {
+    pub dependency: String,
+    pub from: Version,
+    pub to: Version,
+    pub directory: Option<String>,
+}
+
+/// A version from a title, docker digests, submodule SHAs and requirements
+/// like `~> 1.2` stay as they were written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Version {
+    Semver(semver::Version),
+    Raw(String),
+}
+
+impl Version {
+    pub fn parse(s: &str) -> Self {
+        match semver::Version::parse(s.trim_start_matches('v')) {
+            Ok(v) => Version::Semver(v),
+            Err(_) => Version::Raw(s.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Version::Semver(v) => v.fmt(f),
+            Version::Raw(s) => f.write_str(s),
+        }
+    }
+}
+
+/// How large of a change a bump represents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum UpdateType {
+    Patch,
+    Minor,
+    Major,
+    /// The dependency is pinned to a commit SHA (e.g. GitHub Actions pinned
+    /// by SHA) so there is no version to compare
+    ShaPin,
+}
+
+impl fmt::Display for UpdateType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            UpdateType::Patch => "semver-patch",
+            UpdateType::Minor => "semver-minor",
+            UpdateType::Major => "semver-major",
+            UpdateType::ShaPin => "sha-pin",
+        };
+        f.write_str(s)
+    }
+}
+
+impl Bump {
+    /// Both sides of the bump are commit SHAs instead of versions
+    pub fn is_sha(&self) -> bool {
+        self.from.as_deref().is_some_and(looks_like_sha) && looks_like_sha(&self.to)
+    }
+
+    /// Classify this bump, preferring the `update-type` from the dependabot
+    /// metadata in the PR body when it is present
+    pub fn update_type(&self, body: Option<&str>) -> Option<UpdateType> {
+        if let Some(ty) = body.and_then(metadata_update_type) {
+            return Some(ty);
+        }
+        if self.is_sha() {
+            return Some(UpdateType::ShaPin);
+        }
+        compare_versions(self.from.as_deref()?, &self.to)
+    }
+
+    /// The from/to pair, with SHAs shortened to 7 characters
+    pub fn short_versions(&self) -> String {
+        let versions = match &self.from {
+            Some(from) => format!("{} -> {}", short(from), short(&self.to)),
+            None => format!("-> {}", short(&self.to)),
+        };
+        match &self.directory {
+            Some(dir) => format!("{} in {}", versions, dir),
+            None => versions,
+        }
+    }
+}
+
+/// Parse a dependabot title like "Bump actions/checkout from 8f4b7f8 to b4ffde6"
+/// or a renovate title like "chore(deps): update dependency foo to v1.2.3"
+pub fn parse_title(title: &str) -> Option<Bump> {
+    match parse_dependabot_title(title) {
+        Some(bump) => Some(bump.into()),
+        None => parse_renovate_title(strip_conventional_prefix(title.trim())),
+    }
+}
+
+impl DependencyBump {
+    /// Classify this bump from its versions alone, a grouped update's
+    /// metadata doesn't say which update type belongs to which dependency
+    pub fn update_type(&self) -> Option<UpdateType> {
+        let (from, to) = (self.from.to_string(), self.to.to_string());
+        if looks_like_sha(&from) && looks_like_sha(&to) {
+            return Some(UpdateType::ShaPin);
+        }
+        compare_versions(&from, &to)
+    }
+}
+
+/// The group name of a grouped update title like "Bump the cargo group
+/// with 5 updates" or "Bump the npm group across 2 directories with 3 updates"
+pub fn parse_group_title(title: &str) -> Option<&str> {
+    let title = strip_conventional_prefix(title.trim());
+    let rest = strip_prefix_ignore_case(title, "bump the ")?;
+    let (group, _) = rest.split_once(" group ")?;
+    Some(group)
+}
+
+impl From<DependencyBump> for Bump {
+    fn from(bump: DependencyBump) -> Self {
+        Bump {
+            dependency: bump.dependency,
+            from: Some(bump.from.to_string()),
+            to: bump.to.to_string(),
+            directory: bump.directory,
+        }
+    }
+}
+
+/// Parse the dependabot titles
+///
+/// - `Bump serde from 1.0.1 to 1.0.2`
+/// - `Update serde requirement from 1.0 to 2.0`
+/// - `Bump lodash from 4.17.20 to 4.17.21 in /frontend`
+/// - ``Bump node from `1a2b3c4` to `5d6e7f8` `` for docker digests
+/// - `Bump vendor/lib from 1a2b3c4 to 5d6e7f8` for git submodules
+pub fn parse_dependabot_title(title: &str) -> Option<DependencyBump> {
+    let title = strip_conventional_prefix(title.trim());
+    let (dependency, rest) = match strip_prefix_ignore_case(title, "bump ") {
+        Some(rest) => rest.split_once(" from ")?,
+        None => {
+            let rest = strip_prefix_ignore_case(title, "update ")?;
+            let (dependency, rest) = rest.split_once(" from ")?;
+            (dependency.strip_suffix(" requirement")?, rest)
+        }
+    };
+    let (versions, directory) = match rest.rsplit_once(" in ") {
+        Some((versions, dir)) if dir.starts_with('/') => (versions, Some(dir.trim())),
+        _ => (rest, None),
+    };
+    let (from, to) = versions.split_once(" to ")?;
+    let (from, to) = (unquote(from), unquote(to));
+    if dependency.is_empty() || from.is_empty() || to.is_empty() {
+        return None;
+    }
+    Some(DependencyBump {
+        dependency: dependency.trim().to_string(),
+        from: Version::parse(from),
+        to: Version::parse(to),
+        directory: directory.map(str::to_string),
+    })
+}
+
+/// Docker digests are quoted in backticks
+fn unquote(version: &str) -> &str {
+    version.trim().trim_matches('`')
+}
+
+/// Drop a conventional commit prefix like `chore(deps): `
+fn strip_conventional_prefix(title: &str) -> &str {
+    match title.split_once(": ") {
+        Some((prefix, rest)) if !prefix.contains(' ') => rest,
+        _ => title,
+    }
+}
+
+/// Renovate prefixes a manager specific noun to the dependency name
+const RENOVATE_PREFIXES: &[&str] = &["dependency ", "rust crate ", "module ", "gem ", "package "];
+/// Renovate suffixes a manager specific noun to the dependency name
+const RENOVATE_SUFFIXES: &[&str] = &[" action", " docker tag", " digest"];
+
+fn parse_renovate_title(title: &str) -> Option<Bump> {
+    let rest = strip_prefix_ignore_case(title, "update ")?;
+    let (name, rest) = rest.rsplit_once(" to ")?;
+    let mut name = name.trim();
+    for prefix in RENOVATE_PREFIXES {
+        name = strip_prefix_ignore_case(name, prefix).unwrap_or(name);
+    }
+    for suffix in RENOVATE_SUFFIXES {
+        name = strip_suffix_ignore_case(name, suffix).unwrap_or(name);
+    }
+    let to = rest.split_whitespace().next()?;
+    if name.is_empty() || name.contains(' ') {
+        return None;
+    }
+    Some(Bump {
+        dependency: name.to_string(),
+        from: None,
+        to: to.to_string(),
+        directory: None,
+    })
+}
+
+/// Look for an `update-type: version-update:semver-*` line in the
+/// dependabot metadata block
+pub fn metadata_update_type(body: &str) -> Option<UpdateType> {
+    body.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("update-type:")?.trim();
+        match value.strip_prefix("version-update:").unwrap_or(value) {
+            "semver-patch" => Some(UpdateType::Patch),
+            "semver-minor" => Some(UpdateType::Minor),
+            "semver-major" => Some(UpdateType::Major),
+            _ => None,
+        }
+    })
+}
+
+fn compare_versions(from: &str, to: &str) -> Option<UpdateType> {
+    let from = numeric_parts(from)?;
+    let to = numeric_parts(to)?;
+    let part = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+    if part(&from, 0) != part(&to, 0) {
+        Some(UpdateType::Major)
+    } else if part(&from, 1) != part(&to, 1) {
+        Some(UpdateType::Minor)
+    } else {
+        Some(UpdateType::Patch)
+    }
+}
+
+fn numeric_parts(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    core.split('.').map(|p| p.parse().ok()).collect()
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix).then(|| &s[prefix.len()..])
+}
+
+fn strip_suffix_ignore_case<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+    let split = s.len().checked_sub(suffix.len())?;
+    let tail = s.get(split..)?;
+    tail.eq_ignore_ascii_case(suffix).then(|| &s[..split])
+}
+
+fn looks_like_sha(s: &str) -> bool {
+    (7..=40).contains(&s.len())
+        && s.chars().all(|c| c.is_ascii_hexdigit())
+        && !s.chars().all(|c| c.is_ascii_digit())
+}
+
+fn short(s: &str) -> &str {
+    if looks_like_sha(s) {
+        &s[..7]
+    } else {
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dependabot_title_corpus() {
+        // title, dependency, from, to, directory
+        let corpus = [
+            ("Bump serde from 1.0.197 to 1.0.198", "serde", "1.0.197", "1.0.198", None),
+            ("Bump tokio from 1.36.0 to 1.37.0 in /server", "tokio", "1.36.0", "1.37.0", Some("/server")),
+            ("Update serde requirement from 1.0 to 2.0", "serde", "1.0", "2.0", None),
+            (
+                "Update rand requirement from 0.7 to 0.8 in /crates/core",
+                "rand",
+                "0.7",
+                "0.8",
+                Some("/crates/core"),
+            ),
+            (
+                "build(deps): bump lodash from 4.17.20 to 4.17.21 in /frontend",
+                "lodash",
+                "4.17.20",
+                "4.17.21",
+                Some("/frontend"),
+            ),
+            ("chore(deps-dev): Bump @types/node from 20.11.30 to 20.12.7", "@types/node", "20.11.30", "20.12.7", None),
+            ("Bump golang.org/x/net from 0.17.0 to 0.23.0", "golang.org/x/net", "0.17.0", "0.23.0", None),
+            ("Bump node from `1a2b3c4` to `5d6e7f8`", "node", "1a2b3c4", "5d6e7f8", None),
+            ("Bump node from `1a2b3c4` to `5d6e7f8` in /docker", "node", "1a2b3c4", "5d6e7f8", Some("/docker")),
+            ("Bump vendor/libgit2 from 2a0d0bd to 3f5a4b7", "vendor/libgit2", "2a0d0bd", "3f5a4b7", None),
+            ("Bump rails from 7.0.8 to 7.1.3.2", "rails", "7.0.8", "7.1.3.2", None),
+            ("Bump actions/checkout from v3 to v4", "actions/checkout", "v3", "v4", None),
+            // `in` that isn't followed by a path is part of the versions
+            ("Bump thing from 1.0 in beta to 2.0", "thing", "1.0 in beta", "2.0", None),
+        ];
+        for (title, dependency, from, to, directory) in corpus {
+            let bump = parse_dependabot_title(title).unwrap_or_else(|| panic!("{}", title));
+            assert_eq!(bump.dependency, dependency, "{}", title);
+            assert_eq!(bump.from, Version::parse(from), "{}", title);
+            assert_eq!(bump.to, Version::parse(to), "{}", title);
+            assert_eq!(bump.directory.as_deref(), directory, "{}", title);
+            assert_eq!(parse_title(title), Some(bump.into()), "{}", title);
+        }
+    }
+
+    #[test]
+    fn versions_are_semver_when_possible() {
+        let bump = parse_dependabot_title("Bump serde from v1.0.197 to 1.0.198").unwrap();
+        assert_eq!(bump.from, Version::Semver(semver::Version::new(1, 0, 197)));
+        assert_eq!(bump.from.to_string(), "1.0.197");
+        let bump = parse_dependabot_title("Update serde requirement from ~> 1.2 to ~> 2.0").unwrap();
+        assert_eq!(bump.from, Version::Raw("~> 1.2".to_string()));
+        assert_eq!(bump.update_type(), None);
+    }
+
+    #[test]
+    fn not_dependabot_titles() {
+        for title in [
+            "",
+            "Fix the build",
+            "Bump the cargo group with 5 updates",
+            "Bump serde to 1.0.198",
+            "Bump  from 1.0 to 2.0",
+            "Update serde from 1.0 to 2.0",
+            "Bump serde from 1.0 to ",
+        ] {
+            assert_eq!(parse_dependabot_title(title), None, "{:?}", title);
+        }
+    }
+
+    #[test]
+    fn update_types() {
+        let ty = |title: &str| parse_title(title).and_then(|b| b.update_type(None));
+        assert_eq!(ty("Bump serde from 1.0.197 to 1.0.198"), Some(UpdateType::Patch));
+        assert_eq!(ty("Bump tokio from 1.36.0 to 1.37.0"), Some(UpdateType::Minor));
+        assert_eq!(ty("Bump clap from 3.2.25 to 4.5.4"), Some(UpdateType::Major));
+        assert_eq!(ty("Update rand requirement from 0.7 to 0.8"), Some(UpdateType::Minor));
+        assert_eq!(ty("Bump rustls from 0.23.4-alpha.1 to 0.23.4"), Some(UpdateType::Patch));
+        assert_eq!(ty("Bump node from `1a2b3c4` to `5d6e7f8`"), Some(UpdateType::ShaPin));
+        assert_eq!(ty("Bump thing from latest to stable"), None);
+    }
+
+    #[test]
+    fn metadata_update_types() {
+        for (line, expected) in [
+            ("  update-type: version-update:semver-patch", Some(UpdateType::Patch)),
+            ("  update-type: version-update:semver-minor", Some(UpdateType::Minor)),
+            ("update-type: semver-major", Some(UpdateType::Major)),
+            ("  update-type: security-update", None),
+            ("  dependency-type: direct:production", None),
+        ] {
+            assert_eq!(metadata_update_type(line), expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn renovate_title_corpus() {
+        // title, dependency, to
+        let corpus = [
+            ("Update dependency lodash to v4.17.21", "lodash", "v4.17.21"),
+            ("chore(deps): update dependency @types/node to v20.12.7", "@types/node", "v20.12.7"),
+            ("fix(deps): update rust crate serde to 1.0.198", "serde", "1.0.198"),
+            ("Update module github.com/stretchr/testify to v1.9.0", "github.com/stretchr/testify", "v1.9.0"),
+            ("Update gem rails to v7.1.3", "rails", "v7.1.3"),
+            ("Update actions/checkout action to v4", "actions/checkout", "v4"),
+            ("chore(deps): update node docker tag to v20.12", "node", "v20.12"),
+            ("Update golang digest to 7b297d9", "golang", "7b297d9"),
+            ("Update dependency eslint to v9 (major)", "eslint", "v9"),
+        ];
+        for (title, dependency, to) in corpus {
+            let bump = parse_title(title).unwrap_or_else(|| panic!("{}", title));
+            assert_eq!(bump.dependency, dependency, "{}", title);
+            assert_eq!(bump.from, None, "{}", title);
+            assert_eq!(bump.to, to, "{}", title);
+            assert_eq!(bump.update_type(None), None, "{}", title);
+            assert_eq!(bump.short_versions(), format!("-> {}", to), "{}", title);
+        }
+    }
+
+    #[test]
+    fn not_renovate_titles() {
+        for title in [
+            "Update all non-major dependencies",
+            "Update dependency lodash",
+            "Update the readme to explain renovate",
+            "Lock file maintenance",
+        ] {
+            assert_eq!(parse_title(title), None, "{:?}", title);
+        }
+    }
+
+    #[test]
+    fn group_titles() {
+        for (title, group) in [
+            ("Bump the cargo group with 5 updates", "cargo"),
+            ("Bump the npm_and_yarn group across 2 directories with 3 updates", "npm_and_yarn"),
+            ("chore(deps): bump the github-actions group with 2 updates", "github-actions"),
+            ("Bump the production-dependencies group in /frontend with 4 updates", "production-dependencies"),
+        ] {
+            assert_eq!(parse_group_title(title), Some(group), "{}", title);
+            assert_eq!(parse_title(title), None, "{}", title);
+        }
+        assert_eq!(parse_group_title("Bump serde from 1.0.1 to 1.0.2"), None);
+        assert_eq!(parse_group_title("Bump the version"), None);
+    }
+
+    #[test]
+    fn group_members_classify_themselves() {
+        let member = |from: &str, to: &str| DependencyBump {
+            dependency: "tokio".to_string(),
+            from: Version::parse(from),
+            to: Version::parse(to),
+            directory: None,
+        };
+        assert_eq!(member("1.36.0", "1.37.0").update_type(), Some(UpdateType::Minor));
+        assert_eq!(member("1.36.0", "2.0.0").update_type(), Some(UpdateType::Major));
+        assert_eq!(member("704facf", "13aacd8").update_type(), Some(UpdateType::ShaPin));
+    }
+
+    #[test]
+    fn sha_detection() {
+        assert!(looks_like_sha("8f4b7f8"));
+        assert!(looks_like_sha("b4ffde65f46336ab88eb53be808477a3936bae11"));
+        // too short, too long, all digits and not hex
+        assert!(!looks_like_sha("8f4b7f"));
+        assert!(!looks_like_sha("b4ffde65f46336ab88eb53be808477a3936bae110"));
+        assert!(!looks_like_sha("20230101"));
+        assert!(!looks_like_sha("v4.1.1"));
+        assert!(!looks_like_sha("release"));
+    }
+
+    #[test]
+    fn action_pinned_by_full_sha() {
+        let title = "Bump actions/checkout from 8f4b7f84864484a7bf31766abe9204da3cbe65b3 to b4ffde65f46336ab88eb53be808477a3936bae11";
+        let bump = parse_dependabot_title(title).unwrap();
+        assert_eq!(bump.dependency, "actions/checkout");
+        assert_eq!(
+            bump.from,
+            Version::Raw("8f4b7f84864484a7bf31766abe9204da3cbe65b3".to_string())
+        );
+        assert_eq!(bump.update_type(), Some(UpdateType::ShaPin));
+        let bump = parse_title(title).unwrap();
+        assert!(bump.is_sha());
+        assert_eq!(bump.update_type(None), Some(UpdateType::ShaPin));
+        assert_eq!(bump.short_versions(), "8f4b7f8 -> b4ffde6");
+    }
+
+    #[test]
+    fn action_pinned_by_short_sha() {
+        let title = "build(deps): bump actions/cache from 704facf to 13aacd8 in /.github/workflows";
+        let bump = parse_title(title).unwrap();
+        assert_eq!(bump.dependency, "actions/cache");
+        assert_eq!(bump.directory.as_deref(), Some("/.github/workflows"));
+        assert_eq!(bump.update_type(None), Some(UpdateType::ShaPin));
+        assert_eq!(bump.short_versions(), "704facf -> 13aacd8 in /.github/workflows");
+    }
+
+    #[test]
+    fn action_by_tag_is_not_a_sha_pin() {
+        let bump = parse_title("Bump actions/checkout from 3 to 4").unwrap();
+        assert!(!bump.is_sha());
+        assert_eq!(bump.update_type(None), Some(UpdateType::Major));
+        let bump = parse_title("Bump actions/setup-node from 4.0.1 to 4.0.2").unwrap();
+        assert_eq!(bump.update_type(None), Some(UpdateType::Patch));
+    }
+
+    #[test]
+    fn metadata_wins_over_sha() {
+        let bump = parse_title("Bump actions/cache from 704facf to 13aacd8").unwrap();
+        let body = "---\nupdated-dependencies:\n- dependency-name: actions/cache\n  update-type: version-update:semver-minor\n...";
+        assert_eq!(bump.update_type(Some(body)), Some(UpdateType::Minor));
+    }
+}
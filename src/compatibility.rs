@@ -0,0 +1,68 @@
+//! Dependabot's compatibility score badge
+//!
+//! The score itself isn't part of the PR body, the body only links to a
+//! badge that renders the score. Older dependabot-preview PRs link to
+//! `api.dependabot.com` (which github proxies through camo), current PRs
+//! link to `dependabot-badges.githubapp.com`.
+
+/// Find the compatibility score badge url in a dependabot PR body
+pub fn badge_url(body: &str) -> Option<&str> {
+    let start = body.find("compatibility_score")?;
+    let start = body[..start].rfind(['(', '"', '<']).map(|i| i + 1)?;
+    let rest = &body[start..];
+    let end = rest.find([')', '"', '>', ' ', '\n']).unwrap_or(rest.len());
+    let url = &rest[..end];
+    url.starts_with("http").then_some(url)
+}
+
+/// Extract the score percentage from the rendered badge, `None` when the
+/// badge reports the score as unknown
+pub fn parse_badge(svg: &str) -> Option<u8> {
+    // skip over any percentages in the svg attributes before the label
+    let svg = &svg[svg.rfind("compatibility").unwrap_or(0)..];
+    svg.match_indices('%').find_map(|(i, _)| {
+        let digits_start = svg[..i]
+            .rfind(|c: char| !c.is_ascii_digit())
+            .map(|j| j + 1)
+            .unwrap_or(0);
+        svg[digits_start..i].parse::<u8>().ok().filter(|s| *s <= 100)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn badge(score: &str) -> String {
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="156" height="20"><linearGradient id="b" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><g fill="#fff" text-anchor="middle" font-size="11"><text x="52" y="15">compatibility</text><text x="126" y="15">{}</text></g></svg>"##,
+            score
+        )
+    }
+
+    #[test]
+    fn badge_scores() {
+        assert_eq!(parse_badge(&badge("98%")), Some(98));
+        assert_eq!(parse_badge(&badge("100%")), Some(100));
+        assert_eq!(parse_badge(&badge("0%")), Some(0));
+        assert_eq!(parse_badge(&badge("unknown")), None);
+        assert_eq!(parse_badge("compatibility 250%"), None);
+        assert_eq!(parse_badge(""), None);
+    }
+
+    #[test]
+    fn badge_urls() {
+        let current = "[![Dependabot compatibility score](https://dependabot-badges.githubapp.com/badges/compatibility_score?dependency-name=serde&package-manager=cargo&previous-version=1.0.197&new-version=1.0.198)](https://docs.github.com/en/github/managing-security-vulnerabilities/about-dependabot-security-updates#about-compatibility-scores)";
+        assert_eq!(
+            badge_url(current),
+            Some("https://dependabot-badges.githubapp.com/badges/compatibility_score?dependency-name=serde&package-manager=cargo&previous-version=1.0.197&new-version=1.0.198")
+        );
+        let preview = r#"<img src="https://api.dependabot.com/badges/compatibility_score?dependency-name=lodash&package-manager=npm_and_yarn&previous-version=4.17.20&new-version=4.17.21" alt="Dependabot compatibility score">"#;
+        assert_eq!(
+            badge_url(preview),
+            Some("https://api.dependabot.com/badges/compatibility_score?dependency-name=lodash&package-manager=npm_and_yarn&previous-version=4.17.20&new-version=4.17.21")
+        );
+        assert_eq!(badge_url("Bumps serde, no badge here"), None);
+        assert_eq!(badge_url("(compatibility_score)"), None);
+    }
+}
@@ -0,0 +1,279 @@
+//! Default option values from a TOML config file
+//!
+//! Keys are either an option's long name (`user`, `status-username`) or
+//! its field name (`username`, `status_username`) and apply to every
+//! subcommand that has that option. The values become clap defaults so
+//! anything passed on the command line still wins.
+//!
+//! `[profile.<name>]` tables hold the same keys, the one selected with
+//! `--profile` is merged over the top level values.
+
+use clap::{parser::ValueSource, ArgAction, ArgMatches, Command};
+use std::path::{Path, PathBuf};
+
+pub struct Config {
+    pub path: PathBuf,
+    pub profile: Option<String>,
+    /// The top level values with the profile's merged in
+    values: toml::Table,
+}
+
+/// The config file used when `--config` isn't passed
+fn default_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("dependabot-approve").join("config.toml"))
+}
+
+/// Find the value of `flag` in the raw arguments, the config has to be
+/// read before clap parses anything
+fn raw_arg(args: &[String], flag: &str) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == flag {
+            return iter.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix(flag).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Load the `--config` file, or the default file if it exists
+pub fn load(args: &[String]) -> Result<Option<Config>, String> {
+    let profile = raw_arg(args, "--profile");
+    let path = match raw_arg(args, "--config") {
+        Some(path) => PathBuf::from(path),
+        None => match default_path() {
+            Some(path) if path.exists() => path,
+            _ if profile.is_some() => {
+                return Err("--profile requires a config file, none was found".to_string())
+            }
+            _ => return Ok(None),
+        },
+    };
+    read(&path, profile).map(Some)
+}
+
+fn read(path: &Path, profile: Option<String>) -> Result<Config, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read config {}: {}", path.display(), e))?;
+    let mut values = contents
+        .parse::<toml::Table>()
+        .map_err(|e| format!("Unable to parse config {}: {}", path.display(), e))?;
+    let profiles = match values.remove("profile") {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => return Err(format!("`profile` in {} must be a table", path.display())),
+        None => toml::Table::new(),
+    };
+    if let Some(name) = &profile {
+        match profiles.get(name) {
+            Some(toml::Value::Table(profile)) => values.extend(profile.clone()),
+            _ => {
+                let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                return Err(format!(
+                    "No profile {} in {}, available profiles: {}",
+                    name,
+                    path.display(),
+                    if names.is_empty() { "none".to_string() } else { names.join(", ") }
+                ));
+            }
+        }
+    }
+    Ok(Config {
+        path: path.to_path_buf(),
+        profile,
+        values,
+    })
+}
+
+impl Config {
+    /// Use the config values as the defaults of the matching options,
+    /// warning about any keys that don't match an option
+    pub fn apply(&self, cmd: Command) -> Command {
+        for key in self.values.keys() {
+            let known = cmd
+                .get_subcommands()
+                .flat_map(|sub| sub.get_arguments())
+                .any(|arg| arg_matches_key(arg, key));
+            if !known {
+                eprintln!("Warning: unknown config key {} in {}", key, self.path.display());
+            }
+        }
+        cmd.mut_subcommands(|sub| {
+            sub.mut_args(|arg| {
+                let Some(value) = self.value_for(&arg) else {
+                    return arg;
+                };
+                let values = match value_strings(value) {
+                    Some(values) => values,
+                    None => {
+                        eprintln!(
+                            "Warning: unsupported value for {} in {}",
+                            arg.get_id(),
+                            self.path.display()
+                        );
+                        return arg;
+                    }
+                };
+                if matches!(arg.get_action(), ArgAction::SetTrue) && values != ["true"] {
+                    return arg;
+                }
+                arg.default_values(values).required(false)
+            })
+        })
+    }
+
+    fn value_for(&self, arg: &clap::Arg) -> Option<&toml::Value> {
+        self.values
+            .iter()
+            .find(|(key, _)| arg_matches_key(arg, key))
+            .map(|(_, value)| value)
+    }
+
+    /// The ids of the options of the subcommand `sub` that got their value
+    /// from the config
+    pub fn used_keys(&self, sub: &Command, matches: &ArgMatches) -> Vec<String> {
+        sub.get_arguments()
+            .filter(|arg| self.value_for(arg).is_some())
+            .map(|arg| arg.get_id().as_str())
+            .filter(|id| matches.value_source(id) == Some(ValueSource::DefaultValue))
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+fn arg_matches_key(arg: &clap::Arg, key: &str) -> bool {
+    let key = key.replace('-', "_");
+    arg.get_id().as_str() == key
+        || arg
+            .get_long()
+            .is_some_and(|long| long.replace('-', "_") == key)
+}
+
+fn value_strings(value: &toml::Value) -> Option<Vec<String>> {
+    match value {
+        toml::Value::String(s) => Some(vec![s.clone()]),
+        toml::Value::Integer(i) => Some(vec![i.to_string()]),
+        toml::Value::Boolean(b) => Some(vec![b.to_string()]),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(|v| value_strings(v).and_then(|mut v| v.pop()))
+            .collect(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    /// Read `contents` as a config file named `name`
+    fn config(name: &str, contents: &str, profile: Option<&str>) -> Result<Config, String> {
+        let path = std::env::temp_dir().join(format!("dependabot-approve-{}-{}.toml", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        let config = read(&path, profile.map(str::to_string));
+        std::fs::remove_file(&path).unwrap();
+        config
+    }
+
+    fn command() -> Command {
+        Command::new("dependabot-approve").subcommand(
+            Command::new("approve")
+                .arg(Arg::new("username").long("user").required(true))
+                .arg(Arg::new("status_username").long("status-username"))
+                .arg(Arg::new("force").long("force").action(ArgAction::SetTrue))
+                .arg(Arg::new("repos").long("repo").action(ArgAction::Append)),
+        )
+    }
+
+    fn matches(config: &Config, argv: &[&str]) -> ArgMatches {
+        let matches = config.apply(command()).try_get_matches_from(argv).unwrap();
+        matches.subcommand_matches("approve").unwrap().clone()
+    }
+
+    #[test]
+    fn raw_args() {
+        let argv = args(&["dependabot-approve", "approve", "--config", "a.toml", "--profile=work"]);
+        assert_eq!(raw_arg(&argv, "--config"), Some("a.toml".to_string()));
+        assert_eq!(raw_arg(&argv, "--profile"), Some("work".to_string()));
+        assert_eq!(raw_arg(&argv, "--user"), None);
+        // a flag that only shares a prefix isn't a match
+        assert_eq!(raw_arg(&args(&["--configure=x"]), "--config"), None);
+        assert_eq!(raw_arg(&args(&["--config"]), "--config"), None);
+    }
+
+    #[test]
+    fn config_values_are_defaults() {
+        let config = config(
+            "defaults",
+            "user = \"me\"\nstatus-username = \"ci-bot\"\nforce = true\nrepos = [\"a\", \"b\"]\n",
+            None,
+        )
+        .unwrap();
+        let from_config = matches(&config, &["dependabot-approve", "approve"]);
+        assert_eq!(from_config.get_one::<String>("username").unwrap(), "me");
+        assert_eq!(from_config.get_one::<String>("status_username").unwrap(), "ci-bot");
+        assert!(from_config.get_flag("force"));
+        let repos: Vec<&String> = from_config.get_many("repos").unwrap().collect();
+        assert_eq!(repos, ["a", "b"]);
+        let mut used = config.used_keys(command().find_subcommand("approve").unwrap(), &from_config);
+        used.sort();
+        assert_eq!(used, ["force", "repos", "status_username", "username"]);
+
+        let argv = ["dependabot-approve", "approve", "--user", "you", "--repo", "c"];
+        let from_flags = matches(&config, &argv);
+        assert_eq!(from_flags.get_one::<String>("username").unwrap(), "you");
+        let repos: Vec<&String> = from_flags.get_many("repos").unwrap().collect();
+        assert_eq!(repos, ["c"]);
+        let used = config.used_keys(command().find_subcommand("approve").unwrap(), &from_flags);
+        assert_eq!(used, ["status_username", "force"]);
+    }
+
+    #[test]
+    fn false_doesnt_set_a_flag() {
+        let config = config("false-flag", "user = \"me\"\nforce = false\n", None).unwrap();
+        assert!(!matches(&config, &["dependabot-approve", "approve"]).get_flag("force"));
+    }
+
+    #[test]
+    fn profiles_merge_over_the_top_level() {
+        let contents = "user = \"me\"\nstatus-username = \"ci-bot\"\n\n[profile.work]\nuser = \"work-me\"\n\n[profile.home]\nforce = true\n";
+        let work = config("profiles", contents, Some("work")).unwrap();
+        let work = matches(&work, &["dependabot-approve", "approve"]);
+        assert_eq!(work.get_one::<String>("username").unwrap(), "work-me");
+        assert_eq!(work.get_one::<String>("status_username").unwrap(), "ci-bot");
+        assert!(!work.get_flag("force"));
+        let missing = config("profiles", contents, Some("play")).err().unwrap();
+        assert!(missing.starts_with("No profile play in "), "{}", missing);
+        assert!(missing.ends_with("available profiles: home, work"), "{}", missing);
+        let none = config("no-profiles", "user = \"me\"\n", Some("work")).err().unwrap();
+        assert!(none.ends_with("available profiles: none"), "{}", none);
+    }
+
+    #[test]
+    fn bad_files_are_errors() {
+        let err = config("not-toml", "user = ", None).err().unwrap();
+        assert!(err.starts_with("Unable to parse config "), "{}", err);
+        let err = config("profile-value", "profile = \"work\"\n", None).err().unwrap();
+        assert!(err.starts_with("`profile` in "), "{}", err);
+    }
+
+    #[test]
+    fn keys_match_long_names_and_ids() {
+        let arg = Arg::new("status_username").long("status-username");
+        assert!(arg_matches_key(&arg, "status-username"));
+        assert!(arg_matches_key(&arg, "status_username"));
+        assert!(!arg_matches_key(&arg, "status"));
+        assert_eq!(value_strings(&toml::Value::Integer(3)), Some(vec!["3".to_string()]));
+        assert_eq!(value_strings(&toml::Value::Float(1.5)), None);
+    }
+}
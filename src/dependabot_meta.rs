@@ -0,0 +1,216 @@
+//! The details dependabot puts in its PR bodies
+//!
+//! A single update starts with `Bumps [name](url) from A to B.`, a grouped
+//! update starts with `Bumps the <group> group with N updates`, a
+//! `| Package | From | To |` table and an `Updates \`name\` from A to B` line
+//! per dependency. github caps bodies at
+//! 65536 characters so any of the later sections can be cut off, everything
+//! here is optional.
+
+use crate::bump::{self, DependencyBump, UpdateType, Version};
+
+#[derive(Debug, Default)]
+pub struct Meta {
+    /// One entry for a single update, one per dependency for a group
+    pub updates: Vec<DependencyBump>,
+    /// The PR was opened for a security advisory
+    pub security: bool,
+    /// The link to the changelog of the first dependency
+    pub changelog: Option<String>,
+    /// From the `update-type` in the metadata block
+    pub update_type: Option<UpdateType>,
+}
+
+impl Meta {
+    /// The bump of a single update PR
+    pub fn single(&self) -> Option<&DependencyBump> {
+        match self.updates.as_slice() {
+            [bump] => Some(bump),
+            _ => None,
+        }
+    }
+}
+
+/// Read what can be found in a dependabot PR body
+pub fn parse(body: &str) -> Meta {
+    // the table is near the top so it survives truncation better than the
+    // `Updates` lines
+    let mut updates: Vec<DependencyBump> = body.lines().filter_map(table_row).collect();
+    if updates.is_empty() {
+        updates = body.lines().filter_map(update_line).collect();
+    }
+    Meta {
+        updates,
+        security: is_security_update(body),
+        changelog: changelog_url(body),
+        update_type: bump::metadata_update_type(body),
+    }
+}
+
+/// `Bumps [name](url) from A to B.` or ``Updates `name` from A to B``
+fn update_line(line: &str) -> Option<DependencyBump> {
+    let line = line.trim();
+    let rest = line
+        .strip_prefix("Bumps ")
+        .or_else(|| line.strip_prefix("Updates "))?;
+    let (name, rest) = dependency_name(rest)?;
+    let rest = rest.trim_start().strip_prefix("from ")?;
+    let (from, rest) = rest.split_once(" to ")?;
+    let to = rest.split_whitespace().next()?.trim_end_matches('.');
+    if to.is_empty() {
+        return None;
+    }
+    Some(DependencyBump {
+        dependency: name.to_string(),
+        from: Version::parse(from.trim()),
+        to: Version::parse(to),
+        directory: None,
+    })
+}
+
+/// A ``| [name](url) | `A` | `B` |`` row of a grouped update's table
+fn table_row(line: &str) -> Option<DependencyBump> {
+    let cells: Vec<&str> = line.trim().strip_prefix('|')?.strip_suffix('|')?.split('|').map(str::trim).collect();
+    let [name, from, to] = cells.as_slice() else {
+        return None;
+    };
+    let (name, _) = dependency_name(name)?;
+    let version = |cell: &str| {
+        let v = cell.strip_prefix('`')?.strip_suffix('`')?;
+        (!v.is_empty()).then(|| Version::parse(v))
+    };
+    Some(DependencyBump {
+        dependency: name.to_string(),
+        from: version(from)?,
+        to: version(to)?,
+        directory: None,
+    })
+}
+
+/// The name at the start of `[name](url) ...` or `` `name` ... ``, and
+/// what follows it
+fn dependency_name(s: &str) -> Option<(&str, &str)> {
+    if let Some(rest) = s.strip_prefix('[') {
+        let (name, rest) = rest.split_once(']')?;
+        let rest = match rest.strip_prefix('(') {
+            Some(link) => link.split_once(')')?.1,
+            None => rest,
+        };
+        return Some((name, rest));
+    }
+    let rest = s.strip_prefix('`')?;
+    rest.split_once('`')
+}
+
+fn is_security_update(body: &str) -> bool {
+    let lower = body.to_lowercase();
+    lower.contains("includes a security fix") || lower.contains("security vulnerabilit")
+}
+
+/// The first link after the `Changelog` summary
+fn changelog_url(body: &str) -> Option<String> {
+    let start = body.find("<summary>Changelog</summary>")?;
+    let rest = &body[start..];
+    let href = rest.find("href=\"")? + "href=\"".len();
+    let rest = &rest[href..];
+    let url = &rest[..rest.find('"')?];
+    url.starts_with("http").then(|| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SINGLE: &str = r#"Bumps [serde](https://github.com/serde-rs/serde) from 1.0.197 to 1.0.198.
+<details>
+<summary>Release notes</summary>
+<p><em>Sourced from <a href="https://github.com/serde-rs/serde/releases">serde's releases</a>.</em></p>
+</details>
+<details>
+<summary>Changelog</summary>
+<p><em>Sourced from <a href="https://github.com/serde-rs/serde/blob/master/CHANGELOG.md">serde's changelog</a>.</em></p>
+</details>
+<details>
+<summary>Commits</summary>
+</details>
+<br />
+
+
+[![Dependabot compatibility score](https://dependabot-badges.githubapp.com/badges/compatibility_score?dependency-name=serde&package-manager=cargo&previous-version=1.0.197&new-version=1.0.198)](https://docs.github.com/en/github/managing-security-vulnerabilities/about-dependabot-security-updates#about-compatibility-scores)
+
+---
+updated-dependencies:
+- dependency-name: serde
+  dependency-type: direct:production
+  update-type: version-update:semver-patch
+...
+"#;
+
+    const GROUP: &str = r#"Bumps the cargo group with 2 updates: [tokio](https://github.com/tokio-rs/tokio) and [regex](https://github.com/rust-lang/regex).
+
+| Package | From | To |
+| --- | --- | --- |
+| [tokio](https://github.com/tokio-rs/tokio) | `1.36.0` | `1.37.0` |
+| [regex](https://github.com/rust-lang/regex) | `1.10.3` | `1.10.4` |
+
+Updates `tokio` from 1.36.0 to 1.37.0
+Updates `regex` from 1.10.3 to 1.10.4
+"#;
+
+    fn versions(meta: &Meta) -> Vec<(String, String, String)> {
+        meta.updates
+            .iter()
+            .map(|b| (b.dependency.clone(), b.from.to_string(), b.to.to_string()))
+            .collect()
+    }
+
+    fn owned(updates: &[(&str, &str, &str)]) -> Vec<(String, String, String)> {
+        updates
+            .iter()
+            .map(|(d, f, t)| (d.to_string(), f.to_string(), t.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn single_update() {
+        let meta = parse(SINGLE);
+        assert_eq!(versions(&meta), owned(&[("serde", "1.0.197", "1.0.198")]));
+        assert_eq!(meta.single().unwrap().from, Version::parse("1.0.197"));
+        assert!(!meta.security);
+        assert_eq!(
+            meta.changelog.as_deref(),
+            Some("https://github.com/serde-rs/serde/blob/master/CHANGELOG.md")
+        );
+        assert_eq!(meta.update_type, Some(UpdateType::Patch));
+    }
+
+    #[test]
+    fn grouped_update() {
+        let meta = parse(GROUP);
+        let expected = owned(&[("tokio", "1.36.0", "1.37.0"), ("regex", "1.10.3", "1.10.4")]);
+        assert_eq!(versions(&meta), expected);
+        assert!(meta.single().is_none());
+        assert_eq!(meta.update_type, None);
+        // the `Updates` lines are used when the table was cut off
+        let (_, rest) = GROUP.split_once("| Package").unwrap();
+        let (_, updates) = rest.split_once("\n\n").unwrap();
+        assert_eq!(versions(&parse(updates)), expected);
+    }
+
+    #[test]
+    fn security_updates() {
+        let body = "Bumps [lodash](https://github.com/lodash/lodash) from 4.17.20 to 4.17.21.\n\
+                    This update includes a security fix.";
+        let meta = parse(body);
+        assert!(meta.security);
+        assert_eq!(versions(&meta), owned(&[("lodash", "4.17.20", "4.17.21")]));
+    }
+
+    #[test]
+    fn truncated_and_unrelated_bodies() {
+        let meta = parse("Bumps [serde](https://github.com/serde-rs/serde) from 1.0.197 to");
+        assert!(meta.updates.is_empty());
+        let meta = parse("Fixes the build\n\n| a | b | c |\n| --- | --- | --- |");
+        assert!(meta.updates.is_empty() && meta.changelog.is_none() && !meta.security);
+    }
+}
@@ -0,0 +1,222 @@
+//! Errors that end a run, and the exit code for each
+//!
+//! | code | meaning                                                        |
+//! |------|----------------------------------------------------------------|
+//! | 0    | every requested action succeeded, or there was nothing to do   |
+//! | 1    | a request or response failed unexpectedly                      |
+//! | 2    | some of the actions failed, or the arguments were invalid      |
+//! | 3    | no token was found, or it was rejected or lacks permission     |
+//! | 4    | the owner, repo or PR wasn't found                             |
+//! | 5    | the confirmation was abandoned                                 |
+//! | 6    | the --deadline passed before the run finished                  |
+//! | 7    | no PRs were found with --fail-if-empty                         |
+//! | 130  | interrupted with ctrl-c                                        |
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum AppError {
+    /// No token could be found, describing what was tried
+    MissingToken(String),
+    /// github answered with an unsuccessful status
+    Http { status: reqwest::StatusCode, url: String, message: String },
+    /// The token is invalid or expired
+    Unauthorized { url: String, message: String },
+    /// Usually a misspelled owner or repo, or a private repo the token
+    /// can't see
+    NotFound { url: String, message: String },
+    /// A response wasn't the json we expected
+    Parse { context: String, source: serde_json::Error },
+    /// Nothing to do, only a failure with --fail-if-empty
+    NoPrsFound { message: String, fail: bool },
+    /// Some of the approvals, merges, comments or dismissals failed, each
+    /// one was already reported
+    ActionsFailed(usize),
+    /// The token doesn't have permission for the request
+    Forbidden { url: String, message: String },
+    /// The confirmation prompt couldn't get a usable answer
+    UserAborted,
+    /// The org enforces SAML SSO and the token isn't authorized for it
+    SsoRequired { url: Option<String> },
+    /// No more requests are sent once --deadline passes
+    DeadlineExceeded,
+    /// ctrl-c was pressed outside of --wait
+    Interrupted,
+}
+
+impl AppError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::NoPrsFound { fail: false, .. } => 0,
+            AppError::Http { .. } | AppError::Parse { .. } => 1,
+            AppError::ActionsFailed(_) => 2,
+            AppError::MissingToken(_)
+            | AppError::Unauthorized { .. }
+            | AppError::Forbidden { .. }
+            | AppError::SsoRequired { .. } => 3,
+            AppError::NotFound { .. } => 4,
+            AppError::UserAborted => 5,
+            AppError::DeadlineExceeded => 6,
+            AppError::NoPrsFound { fail: true, .. } => 7,
+            AppError::Interrupted => 130,
+        }
+    }
+
+    /// Errors caused by the token rather than the request, every other
+    /// request would fail the same way
+    pub fn is_global(&self) -> bool {
+        matches!(
+            self,
+            AppError::Unauthorized { .. } | AppError::Forbidden { .. } | AppError::SsoRequired { .. }
+        )
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::MissingToken(msg) | AppError::NoPrsFound { message: msg, .. } => f.write_str(msg),
+            AppError::ActionsFailed(1) => f.write_str("1 action failed"),
+            AppError::ActionsFailed(count) => write!(f, "{} actions failed", count),
+            AppError::Forbidden { url, message } => write!(
+                f,
+                "github refused {}{}, the token doesn't have permission for it",
+                url,
+                with_message(message)
+            ),
+            AppError::Http { status, url, message } => {
+                write!(f, "Request to {} failed: {}{}", url, status, with_message(message))
+            }
+            AppError::Unauthorized { url, message } => write!(
+                f,
+                "github rejected the token for {}{}, it's invalid or expired\n\
+                 Tokens are read from --app-id, --api-key, --key-path, --key-ring-file, --keyring, \
+                 --use-gh-auth and then the GITHUB_TOKEN/GH_TOKEN env vars, the first one set is used",
+                url,
+                with_message(message)
+            ),
+            AppError::NotFound { url, message } => write!(
+                f,
+                "{} was not found{}\n\
+                 Check the owner and repo are spelled correctly and that the token can see private repos",
+                url,
+                with_message(message)
+            ),
+            AppError::Parse { context, source } => write!(f, "Unable to parse {}: {}", context, source),
+            AppError::UserAborted => f.write_str("Failed to parse input 5 times, exiting"),
+            AppError::DeadlineExceeded => f.write_str("The --deadline passed before the run finished"),
+            AppError::Interrupted => f.write_str("Interrupted"),
+            AppError::SsoRequired { url: Some(url) } => write!(
+                f,
+                "The organization requires SAML SSO and the token hasn't been authorized for it\n\
+                 Open {} to authorize the token, then run this again",
+                url
+            ),
+            AppError::SsoRequired { url: None } => f.write_str(
+                "The organization requires SAML SSO and the token hasn't been authorized for it\n\
+                 Authorize the token for the organization in your github token settings, then run this again",
+            ),
+        }
+    }
+}
+
+/// The message from github's error body, if there was one
+fn with_message(message: &str) -> String {
+    if message.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", message)
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Parse { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// The exit code for `e`, anything that isn't an `AppError` exits with 1
+pub fn code(e: &(dyn std::error::Error + 'static)) -> i32 {
+    e.downcast_ref::<AppError>().map_or(1, AppError::exit_code)
+}
+
+/// Report `e` and exit with its code
+pub fn exit(e: Box<dyn std::error::Error>) -> ! {
+    match e.downcast_ref::<AppError>() {
+        // not a failure, but stdout is reserved for the --output document
+        Some(e @ AppError::NoPrsFound { .. }) if !crate::output::is_machine() => println!("{}", e),
+        Some(e) => eprintln!("{}", e),
+        None => eprintln!("Error: {}", e),
+    }
+    std::process::exit(code(e.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url() -> String {
+        "https://api.github.com/repos/owner/repo".to_string()
+    }
+
+    #[test]
+    fn exit_codes() {
+        let parse = serde_json::from_str::<u8>("<html>").unwrap_err();
+        let cases = [
+            (AppError::NoPrsFound { message: String::new(), fail: false }, 0),
+            (
+                AppError::Http {
+                    status: reqwest::StatusCode::BAD_GATEWAY,
+                    url: url(),
+                    message: String::new(),
+                },
+                1,
+            ),
+            (AppError::Parse { context: url(), source: parse }, 1),
+            (AppError::ActionsFailed(2), 2),
+            (AppError::MissingToken(String::new()), 3),
+            (AppError::Unauthorized { url: url(), message: String::new() }, 3),
+            (AppError::Forbidden { url: url(), message: String::new() }, 3),
+            (AppError::SsoRequired { url: None }, 3),
+            (AppError::NotFound { url: url(), message: String::new() }, 4),
+            (AppError::UserAborted, 5),
+            (AppError::DeadlineExceeded, 6),
+            (AppError::NoPrsFound { message: String::new(), fail: true }, 7),
+            (AppError::Interrupted, 130),
+        ];
+        for (e, expected) in cases {
+            assert_eq!(e.exit_code(), expected, "{:?}", e);
+            let boxed: Box<dyn std::error::Error> = e.into();
+            assert_eq!(code(boxed.as_ref()), expected);
+        }
+        let other: Box<dyn std::error::Error> = "something else".into();
+        assert_eq!(code(other.as_ref()), 1);
+    }
+
+    #[test]
+    fn global_errors() {
+        assert!(AppError::Unauthorized { url: url(), message: String::new() }.is_global());
+        assert!(AppError::Forbidden { url: url(), message: String::new() }.is_global());
+        assert!(AppError::SsoRequired { url: None }.is_global());
+        assert!(!AppError::NotFound { url: url(), message: String::new() }.is_global());
+        assert!(!AppError::ActionsFailed(1).is_global());
+    }
+
+    #[test]
+    fn messages() {
+        assert_eq!(AppError::ActionsFailed(1).to_string(), "1 action failed");
+        assert_eq!(AppError::ActionsFailed(3).to_string(), "3 actions failed");
+        let e = AppError::Http {
+            status: reqwest::StatusCode::BAD_GATEWAY,
+            url: url(),
+            message: "upstream".to_string(),
+        };
+        assert_eq!(
+            e.to_string(),
+            "Request to https://api.github.com/repos/owner/repo failed: 502 Bad Gateway (upstream)"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Conditional GETs with `If-None-Match`
+//!
+//! github doesn't count `304 Not Modified` responses against the rate
+//! limit, so remembering the ETag and body of each url makes polling with
+//! `--wait` close to free. With `--etag-cache` the entries are kept in a
+//! file so repeated runs benefit too.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Entry {
+    pub etag: String,
+    pub body: String,
+}
+
+pub struct Cache {
+    entries: Mutex<BTreeMap<String, Entry>>,
+    /// Where to save the entries once the run is done, `None` to only keep
+    /// them in memory
+    path: Option<PathBuf>,
+}
+
+/// The cache file used by `--etag-cache`
+fn cache_path() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(base.join("dependabot-approve").join("etags.json"))
+}
+
+impl Cache {
+    pub fn in_memory() -> Self {
+        Self {
+            entries: Mutex::new(BTreeMap::new()),
+            path: None,
+        }
+    }
+
+    /// Load the entries saved by a previous run, a missing or broken cache
+    /// file only means starting empty
+    pub fn persisted() -> Self {
+        let Some(path) = cache_path() else {
+            eprintln!("Warning: unable to find a cache directory, ETags won't be saved");
+            return Self::in_memory();
+        };
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Warning: ignoring the unreadable cache {}: {}", path.display(), e);
+                BTreeMap::new()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                eprintln!("Warning: unable to read the cache {}: {}", path.display(), e);
+                BTreeMap::new()
+            }
+        };
+        Self {
+            entries: Mutex::new(entries),
+            path: Some(path),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> Option<Entry> {
+        self.entries.lock().expect("etag lock").get(url).cloned()
+    }
+
+    pub fn insert(&self, url: &str, entry: Entry) {
+        self.entries.lock().expect("etag lock").insert(url.to_string(), entry);
+    }
+
+    fn save(&self, path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let entries = self.entries.lock().expect("etag lock");
+        std::fs::write(path, serde_json::to_string(&*entries)?)?;
+        Ok(())
+    }
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = self.save(path) {
+                eprintln!("Warning: unable to save the cache {}: {}", path.display(), e);
+            }
+        }
+    }
+}
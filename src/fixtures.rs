@@ -0,0 +1,70 @@
+//! Saving github's responses with `--record` and serving them back with
+//! `--replay`
+//!
+//! Every successful GET is saved as `<dir>/<sanitized url>.json`. When
+//! replaying nothing is sent to github, GETs are answered from the saved
+//! files and writes are only logged.
+
+use std::path::PathBuf;
+
+pub enum Fixtures {
+    Off,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+impl Fixtures {
+    pub fn new(record: Option<&str>, replay: Option<&str>) -> Self {
+        match (record, replay) {
+            (_, Some(dir)) => Fixtures::Replay(dir.into()),
+            (Some(dir), None) => Fixtures::Record(dir.into()),
+            (None, None) => Fixtures::Off,
+        }
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self, Fixtures::Replay(_))
+    }
+
+    /// The body recorded for `url`
+    pub fn load(&self, url: &str) -> Option<String> {
+        let Fixtures::Replay(dir) = self else {
+            return None;
+        };
+        let path = dir.join(file_name(url));
+        match std::fs::read_to_string(&path) {
+            Ok(body) => Some(body),
+            Err(e) => {
+                eprintln!("Warning: no recorded response for {} in {}: {}", url, path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Save the body of a GET of `url` when recording
+    pub fn save(&self, url: &str, body: &str) {
+        let Fixtures::Record(dir) = self else {
+            return;
+        };
+        let path = dir.join(file_name(url));
+        let res = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, body));
+        if let Err(e) = res {
+            eprintln!("Warning: unable to record {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self, Fixtures::Record(_))
+    }
+}
+
+/// A file name for `url`, anything other than letters, numbers, `-` and `.`
+/// becomes `_`
+pub fn file_name(url: &str) -> String {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let name: String = url
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect();
+    format!("{}.json", name)
+}
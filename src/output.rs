@@ -0,0 +1,495 @@
+//! `--output json` and `--output csv`, a single document describing the run
+//!
+//! The document goes to stdout unless `--output-file` is passed. When it's
+//! on stdout everything meant for people moves to stderr (see the
+//! `println!` override in main.rs) so stdout can be piped straight into jq.
+//!
+//! `--output github-actions` prints a `::notice` or `::error` workflow
+//! command per PR instead, mixed in with the human output, and appends a
+//! markdown table to the `GITHUB_STEP_SUMMARY` file when there is one.
+
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+use time::OffsetDateTime;
+
+static MACHINE: AtomicBool = AtomicBool::new(false);
+/// What happened to each PR, keyed by `owner/repo#number`
+static OUTCOMES: Mutex<BTreeMap<String, Recorded>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+    GithubActions,
+}
+
+/// `to_file` is set when the document goes to --output-file and stdout is
+/// free for people
+pub fn set_format(format: Format, to_file: bool) {
+    let document = matches!(format, Format::Json | Format::Csv);
+    MACHINE.store(document && !to_file, Ordering::Relaxed);
+}
+
+/// stdout is reserved for the document
+pub fn is_machine() -> bool {
+    MACHINE.load(Ordering::Relaxed)
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum Outcome {
+    Done { action: &'static str },
+    /// --dry-run or --no-approve, `action` is what would have been done
+    DryRun { action: String },
+    Skipped { reason: String },
+    Failed { error: String },
+}
+
+impl Outcome {
+    fn name(&self) -> &'static str {
+        match self {
+            Outcome::Done { .. } => "done",
+            Outcome::DryRun { .. } => "dry_run",
+            Outcome::Skipped { .. } => "skipped",
+            Outcome::Failed { .. } => "failed",
+        }
+    }
+
+    /// How much an outcome says about a PR, a failed follow up outweighs
+    /// the approval before it
+    fn severity(&self) -> u8 {
+        match self {
+            Outcome::Done { .. } => 0,
+            Outcome::DryRun { .. } => 1,
+            Outcome::Skipped { .. } => 2,
+            Outcome::Failed { .. } => 3,
+        }
+    }
+
+    /// What happened, in a few words
+    fn describe(&self) -> String {
+        match self {
+            Outcome::Done { action } => action.to_string(),
+            Outcome::DryRun { action } => action.clone(),
+            Outcome::Skipped { reason } => format!("skipped: {}", reason),
+            Outcome::Failed { error } => format!("failed: {}", error),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Recorded {
+    #[serde(flatten)]
+    pub outcome: Outcome,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+}
+
+/// Record what happened to a PR, the most severe outcome wins and the
+/// first one recorded wins a tie since it's the most specific one
+pub fn record(key: String, outcome: Outcome) {
+    let mut outcomes = OUTCOMES.lock().expect("outcomes lock");
+    let replace = outcomes
+        .get(&key)
+        .is_none_or(|recorded| outcome.severity() > recorded.outcome.severity());
+    if replace {
+        outcomes.insert(
+            key,
+            Recorded {
+                outcome,
+                at: OffsetDateTime::now_utc(),
+            },
+        );
+    }
+}
+
+pub fn outcome(key: &str) -> Option<Recorded> {
+    OUTCOMES.lock().expect("outcomes lock").get(key).cloned()
+}
+
+#[derive(Serialize, Debug)]
+pub struct Document {
+    /// The options of the run, without any tokens
+    pub parameters: serde_json::Value,
+    pub prs: Vec<PrEntry>,
+    /// How many PRs ended with each result
+    pub summary: BTreeMap<&'static str, usize>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PrEntry {
+    pub repo: String,
+    pub number: u32,
+    pub title: String,
+    pub url: String,
+    pub dependency: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub status: String,
+    #[serde(flatten)]
+    pub result: Recorded,
+}
+
+/// The columns of `--output csv`
+const CSV_HEADER: &[&str] = &[
+    "repo",
+    "pr_number",
+    "title",
+    "dependency",
+    "from_version",
+    "to_version",
+    "status",
+    "action",
+    "timestamp",
+];
+
+impl Document {
+    pub fn new(parameters: serde_json::Value, prs: Vec<PrEntry>) -> Self {
+        let mut summary = BTreeMap::new();
+        for pr in &prs {
+            *summary.entry(pr.result.outcome.name()).or_default() += 1;
+        }
+        Self {
+            parameters,
+            prs,
+            summary,
+        }
+    }
+
+    pub fn failed(&self) -> usize {
+        self.summary.get("failed").copied().unwrap_or(0)
+    }
+
+    /// The counts of each result, and which PRs failed. Only printed with
+    /// `quiet` when something failed
+    pub fn print_summary(&self, quiet: bool) {
+        let failed: Vec<String> = self
+            .prs
+            .iter()
+            .filter(|pr| matches!(pr.result.outcome, Outcome::Failed { .. }))
+            .map(|pr| format!("#{}", pr.number))
+            .collect();
+        if quiet && failed.is_empty() {
+            return;
+        }
+        let count = |name| self.summary.get(name).copied().unwrap_or(0);
+        println!(
+            "Summary: {} done, {} dry run, {} skipped, {} failed",
+            count("done"),
+            count("dry_run"),
+            count("skipped"),
+            count("failed")
+        );
+        if !failed.is_empty() {
+            let line = format!("Failed: {}", failed.join(", "));
+            println!("{}", crate::style::paint(line, crate::style::Color::Red));
+        }
+    }
+
+    /// Write the document to `path`, or stdout without one
+    pub fn write(&self, format: Format, path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let text = match format {
+            Format::Text => return Ok(()),
+            Format::Json => serde_json::to_string_pretty(self)?,
+            Format::Csv => self.to_csv(),
+            Format::GithubActions => {
+                self.append_step_summary()?;
+                self.to_annotations()
+            }
+        };
+        match path {
+            Some(path) => std::fs::write(path, text + "\n")
+                .map_err(|e| format!("Unable to write {}: {}", path, e).into()),
+            None => {
+                std::println!("{}", text);
+                Ok(())
+            }
+        }
+    }
+
+    /// One row per PR, the run parameters and summary are left out
+    fn to_csv(&self) -> String {
+        let mut lines = vec![CSV_HEADER.join(",")];
+        for pr in &self.prs {
+            let action = pr.result.outcome.describe();
+            let at = pr
+                .result
+                .at
+                .format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or_default();
+            let row = [
+                pr.repo.as_str(),
+                &pr.number.to_string(),
+                &pr.title,
+                pr.dependency.as_deref().unwrap_or_default(),
+                pr.from.as_deref().unwrap_or_default(),
+                pr.to.as_deref().unwrap_or_default(),
+                &pr.status,
+                &action,
+                &at,
+            ];
+            let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            lines.push(row.join(","));
+        }
+        lines.join("\n")
+    }
+
+    /// A workflow command per PR, failures are `::error` so they show up
+    /// in the job's annotations
+    fn to_annotations(&self) -> String {
+        let lines: Vec<String> = self
+            .prs
+            .iter()
+            .map(|pr| {
+                let level = match pr.result.outcome {
+                    Outcome::Failed { .. } => "error",
+                    _ => "notice",
+                };
+                let title = format!("{}#{}", pr.repo, pr.number);
+                let message = format!("{}: {}", pr.title, pr.result.outcome.describe());
+                format!(
+                    "::{} title={}::{}",
+                    level,
+                    escape_property(&title),
+                    escape_data(&message)
+                )
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    /// Add a table of the PRs to the file in `GITHUB_STEP_SUMMARY`, if set
+    fn append_step_summary(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY").filter(|p| !p.is_empty()) else {
+            return Ok(());
+        };
+        let mut text = String::from("### dependabot-approve\n\n");
+        if self.prs.is_empty() {
+            text.push_str("No PRs were considered\n");
+        } else {
+            text.push_str("| PR | Title | Dependency | Status | Result |\n");
+            text.push_str("|----|-------|------------|--------|--------|\n");
+            for pr in &self.prs {
+                let dependency = match (&pr.dependency, &pr.from, &pr.to) {
+                    (Some(dependency), Some(from), Some(to)) => format!("{} {} → {}", dependency, from, to),
+                    (Some(dependency), ..) => dependency.clone(),
+                    _ => String::new(),
+                };
+                let cells = [
+                    format!("[{}#{}]({})", pr.repo, pr.number, pr.url),
+                    pr.title.clone(),
+                    dependency,
+                    pr.status.clone(),
+                    pr.result.outcome.describe(),
+                ];
+                let cells: Vec<String> = cells.iter().map(|cell| markdown_cell(cell)).collect();
+                text.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+        }
+        let counts: Vec<String> = self
+            .summary
+            .iter()
+            .map(|(name, count)| format!("{} {}", count, name.replace('_', " ")))
+            .collect();
+        if !counts.is_empty() {
+            text.push_str(&format!("\n{}\n", counts.join(", ")));
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|e| format!("Unable to write the step summary {}: {}", path.to_string_lossy(), e).into())
+    }
+}
+
+/// Escape the message of a workflow command
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escape a `key=value` property of a workflow command
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Keep a table cell on one line and out of the column separators
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Quote a field that has a comma, quote or newline, doubling any quotes
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn entry(number: u32, title: &str, outcome: Outcome) -> PrEntry {
+        PrEntry {
+            repo: "owner/repo".to_string(),
+            number,
+            title: title.to_string(),
+            url: format!("https://github.com/owner/repo/pull/{}", number),
+            dependency: Some("serde".to_string()),
+            from: Some("1.0.1".to_string()),
+            to: Some("1.0.2".to_string()),
+            status: "success".to_string(),
+            result: Recorded {
+                outcome,
+                at: datetime!(2024-05-01 12:00:00 UTC),
+            },
+        }
+    }
+
+    fn document() -> Document {
+        Document::new(
+            serde_json::json!({ "dry_run": false, "force": true }),
+            vec![
+                entry(1, "Bump serde from 1.0.1 to 1.0.2", Outcome::Done { action: "approve" }),
+                entry(2, "Bump serde, again", Outcome::DryRun { action: "would_approve".to_string() }),
+                entry(3, "Bump \"serde\"", Outcome::Skipped { reason: "rerun requested".to_string() }),
+                entry(4, "Bump serde\nagain", Outcome::Failed { error: "403 Forbidden".to_string() }),
+            ],
+        )
+    }
+
+    #[test]
+    fn json_schema() {
+        let json = serde_json::to_value(document()).unwrap();
+        assert_eq!(json["parameters"], serde_json::json!({ "dry_run": false, "force": true }));
+        assert_eq!(
+            json["prs"][0],
+            serde_json::json!({
+                "repo": "owner/repo",
+                "number": 1,
+                "title": "Bump serde from 1.0.1 to 1.0.2",
+                "url": "https://github.com/owner/repo/pull/1",
+                "dependency": "serde",
+                "from": "1.0.1",
+                "to": "1.0.2",
+                "status": "success",
+                "result": "done",
+                "action": "approve",
+                "at": "2024-05-01T12:00:00Z",
+            })
+        );
+        assert_eq!(json["prs"][1]["result"], "dry_run");
+        assert_eq!(json["prs"][1]["action"], "would_approve");
+        assert_eq!(json["prs"][2]["result"], "skipped");
+        assert_eq!(json["prs"][2]["reason"], "rerun requested");
+        assert_eq!(json["prs"][3]["result"], "failed");
+        assert_eq!(json["prs"][3]["error"], "403 Forbidden");
+        assert_eq!(
+            json["summary"],
+            serde_json::json!({ "done": 1, "dry_run": 1, "skipped": 1, "failed": 1 })
+        );
+        assert_eq!(document().failed(), 1);
+    }
+
+    #[test]
+    fn csv_rows() {
+        let csv = document().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "repo,pr_number,title,dependency,from_version,to_version,status,action,timestamp");
+        assert_eq!(
+            lines[1],
+            "owner/repo,1,Bump serde from 1.0.1 to 1.0.2,serde,1.0.1,1.0.2,success,approve,2024-05-01T12:00:00Z"
+        );
+        assert_eq!(
+            lines[2],
+            "owner/repo,2,\"Bump serde, again\",serde,1.0.1,1.0.2,success,would_approve,2024-05-01T12:00:00Z"
+        );
+        assert_eq!(
+            lines[3],
+            "owner/repo,3,\"Bump \"\"serde\"\"\",serde,1.0.1,1.0.2,success,skipped: rerun requested,2024-05-01T12:00:00Z"
+        );
+        // a quoted newline keeps the row together
+        assert_eq!(lines[4], "owner/repo,4,\"Bump serde");
+        assert_eq!(lines[5], "again\",serde,1.0.1,1.0.2,success,failed: 403 Forbidden,2024-05-01T12:00:00Z");
+        assert_eq!(lines.len(), 6);
+    }
+
+    #[test]
+    fn csv_missing_versions() {
+        let mut pr = entry(5, "Bump the cargo group with 2 updates", Outcome::Done { action: "merge" });
+        pr.dependency = None;
+        pr.from = None;
+        pr.to = None;
+        let csv = Document::new(serde_json::json!({}), vec![pr]).to_csv();
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("owner/repo,5,Bump the cargo group with 2 updates,,,,success,merge,2024-05-01T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn output_file() {
+        let path = std::env::temp_dir().join(format!("dependabot-approve-{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        document().write(Format::Csv, Some(path)).unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), document().to_csv() + "\n");
+        std::fs::remove_file(path).unwrap();
+        let err = document().write(Format::Csv, Some("/nonexistent/dir/out.csv")).unwrap_err();
+        assert!(err.to_string().starts_with("Unable to write /nonexistent/dir/out.csv"), "{}", err);
+    }
+
+    #[test]
+    fn annotations() {
+        let annotations = document().to_annotations();
+        let lines: Vec<&str> = annotations.lines().collect();
+        assert_eq!(lines[0], "::notice title=owner/repo#1::Bump serde from 1.0.1 to 1.0.2: approve");
+        assert_eq!(lines[1], "::notice title=owner/repo#2::Bump serde, again: would_approve");
+        assert_eq!(lines[2], "::notice title=owner/repo#3::Bump \"serde\": skipped: rerun requested");
+        assert_eq!(lines[3], "::error title=owner/repo#4::Bump serde%0Aagain: failed: 403 Forbidden");
+        assert_eq!(lines.len(), 4);
+        assert_eq!(escape_property("a:b,c%"), "a%3Ab%2Cc%25");
+    }
+
+    #[test]
+    fn step_summary() {
+        let path = std::env::temp_dir().join(format!("dependabot-approve-{}-summary.md", std::process::id()));
+        std::fs::write(&path, "earlier step\n").unwrap();
+        // only the step summary reads this
+        std::env::set_var("GITHUB_STEP_SUMMARY", &path);
+        let res = document().append_step_summary();
+        std::env::remove_var("GITHUB_STEP_SUMMARY");
+        res.unwrap();
+        let summary = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines[..3], ["earlier step", "### dependabot-approve", ""]);
+        assert_eq!(lines[3], "| PR | Title | Dependency | Status | Result |");
+        assert_eq!(
+            lines[5],
+            "| [owner/repo#1](https://github.com/owner/repo/pull/1) | Bump serde from 1.0.1 to 1.0.2 | serde 1.0.1 → 1.0.2 | success | approve |"
+        );
+        assert_eq!(
+            lines[8],
+            "| [owner/repo#4](https://github.com/owner/repo/pull/4) | Bump serde again | serde 1.0.1 → 1.0.2 | success | failed: 403 Forbidden |"
+        );
+        assert_eq!(lines[10], "1 done, 1 dry run, 1 failed, 1 skipped");
+        assert_eq!(markdown_cell("a|b"), "a\\|b");
+    }
+
+    #[test]
+    fn empty_json() {
+        let json = serde_json::to_value(Document::new(serde_json::json!({}), Vec::new())).unwrap();
+        assert_eq!(json, serde_json::json!({ "parameters": {}, "prs": [], "summary": {} }));
+    }
+}
@@ -0,0 +1,117 @@
+//! A one line `fetching statuses 17/40` progress indicator on stderr
+//!
+//! Only shown when stderr is a terminal, outside of --quiet and when stdout
+//! isn't carrying an --output document. Anything printed while it's shown,
+//! including log records, clears the line first and redraws it after.
+
+use std::{
+    io::{IsTerminal, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// The line currently drawn, if any
+static LINE: Mutex<Option<String>> = Mutex::new(None);
+
+pub struct Progress {
+    label: &'static str,
+    total: usize,
+    done: AtomicUsize,
+    enabled: bool,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, total: usize, quiet: bool) -> Self {
+        let enabled = total > 1 && !quiet && !crate::output::is_machine() && std::io::stderr().is_terminal();
+        let progress = Self {
+            label,
+            total,
+            done: AtomicUsize::new(0),
+            enabled,
+        };
+        progress.draw(0);
+        progress
+    }
+
+    /// Count one more item as done
+    pub fn inc(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        self.draw(done);
+    }
+
+    fn draw(&self, done: usize) {
+        if !self.enabled {
+            return;
+        }
+        let line = format!("{} {}/{}", self.label, done, self.total);
+        let mut current = LINE.lock().expect("progress lock");
+        clear(current.is_some());
+        eprint_raw(&line);
+        *current = Some(line);
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.enabled {
+            let mut current = LINE.lock().expect("progress lock");
+            clear(current.take().is_some());
+        }
+    }
+}
+
+/// Run `f` with the progress line cleared, redrawing it afterwards
+pub fn suspend<T>(f: impl FnOnce() -> T) -> T {
+    let line = LINE.lock().expect("progress lock").clone();
+    clear(line.is_some());
+    let ret = f();
+    if let Some(line) = line {
+        eprint_raw(&line);
+    }
+    ret
+}
+
+fn clear(drawn: bool) {
+    if drawn {
+        eprint_raw("\r\x1b[2K");
+    }
+}
+
+fn eprint_raw(text: &str) {
+    let mut stderr = std::io::stderr();
+    let _ = stderr.write_all(text.as_bytes());
+    let _ = stderr.flush();
+}
+
+/// Logs through pretty_env_logger with the progress line out of the way
+struct Logger(Box<dyn log::Log>);
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.0.enabled(record.metadata()) {
+            suspend(|| self.0.log(record));
+        }
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
+
+/// `pretty_env_logger::init` with the records routed around the progress
+/// line
+pub fn init_logger() {
+    let mut builder = pretty_env_logger::formatted_builder();
+    if let Ok(filters) = std::env::var("RUST_LOG") {
+        builder.parse_filters(&filters);
+    }
+    let logger = builder.build();
+    log::set_max_level(logger.filter());
+    let _ = log::set_boxed_logger(Box::new(Logger(Box::new(logger))));
+}
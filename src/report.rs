@@ -0,0 +1,107 @@
+//! `--report`, a json record of the run for auditing
+//!
+//! The report is started once the options are known and written from
+//! `main` when the run returns, or from `Guard` when it unwinds, so a run
+//! that fails partway still leaves one behind with whatever was known.
+
+use crate::output::{Document, PrEntry};
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+use time::OffsetDateTime;
+
+/// The report being collected, taken when it's written so that only happens once
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+static REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+struct Pending {
+    path: String,
+    started_at: OffsetDateTime,
+    parameters: serde_json::Value,
+    prs: Vec<PrEntry>,
+    rate_limit_remaining: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    #[serde(with = "time::serde::rfc3339")]
+    started_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    finished_at: OffsetDateTime,
+    exit_code: i32,
+    error: Option<String>,
+    #[serde(flatten)]
+    document: Document,
+    /// Requests sent to github, counting each retry
+    requests: usize,
+    rate_limit_remaining: Option<u64>,
+}
+
+/// Start collecting a report to write to `template`, with any `{date}`
+/// replaced by the UTC start time as YYYYMMDD-HHMMSS
+pub fn start(template: &str, parameters: serde_json::Value) {
+    let started_at = OffsetDateTime::now_utc();
+    let format = time::macros::format_description!("[year][month][day]-[hour][minute][second]");
+    let date = started_at.format(&format).unwrap_or_default();
+    *PENDING.lock().expect("report lock") = Some(Pending {
+        path: template.replace("{date}", &date),
+        started_at,
+        parameters,
+        prs: Vec::new(),
+        rate_limit_remaining: None,
+    });
+}
+
+/// The PRs considered so far and what happened to them, replacing any set before
+pub fn set_prs(prs: Vec<PrEntry>) {
+    if let Some(pending) = PENDING.lock().expect("report lock").as_mut() {
+        pending.prs = prs;
+    }
+}
+
+pub fn count_request() {
+    REQUESTS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn set_rate_limit_remaining(remaining: u64) {
+    if let Some(pending) = PENDING.lock().expect("report lock").as_mut() {
+        pending.rate_limit_remaining = Some(remaining);
+    }
+}
+
+/// Write the report if one was started and not written yet, a failure to
+/// write it is only a warning
+pub fn finish(exit_code: i32, error: Option<String>) {
+    let Some(pending) = PENDING.lock().expect("report lock").take() else {
+        return;
+    };
+    let report = Report {
+        started_at: pending.started_at,
+        finished_at: OffsetDateTime::now_utc(),
+        exit_code,
+        error,
+        document: Document::new(pending.parameters, pending.prs),
+        requests: REQUESTS.load(Ordering::Relaxed),
+        rate_limit_remaining: pending.rate_limit_remaining,
+    };
+    let path = pending.path;
+    let written = serde_json::to_string_pretty(&report)
+        .map_err(|e| e.to_string())
+        .and_then(|text| std::fs::write(&path, text + "\n").map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        eprintln!("Warning: unable to write the report {}: {}", path, e);
+    }
+}
+
+/// Writes the report if the run panics before `finish` is reached
+pub struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            finish(101, Some("the run panicked".to_string()));
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Commit status states and the `--filter` values that match them
+
+use serde::{Deserialize, Deserializer};
+use std::{fmt, str::FromStr};
+
+/// The state of a commit status, github only sends the first four but GHE
+/// and other CI integrations have been seen sending others
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatusState {
+    Pending,
+    Success,
+    Failure,
+    Error,
+    Other(String),
+    /// The PR has no statuses at all, matched by `none` in --filter
+    Missing,
+}
+
+/// The states with a name of their own, for matching filters against
+const KNOWN: &[StatusState] = &[
+    StatusState::Pending,
+    StatusState::Success,
+    StatusState::Failure,
+    StatusState::Error,
+];
+
+impl StatusState {
+    pub fn as_str(&self) -> &str {
+        match self {
+            StatusState::Pending => "pending",
+            StatusState::Success => "success",
+            StatusState::Failure => "failure",
+            StatusState::Error => "error",
+            StatusState::Other(s) => s,
+            StatusState::Missing => "(none)",
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        *self == StatusState::Pending
+    }
+
+    pub fn is_success(&self) -> bool {
+        *self == StatusState::Success
+    }
+
+    /// How bad a state is when combining contexts, the worst one wins
+    pub fn severity(&self) -> u8 {
+        match self {
+            StatusState::Success => 0,
+            StatusState::Pending => 1,
+            StatusState::Error => 3,
+            StatusState::Failure | StatusState::Other(_) | StatusState::Missing => 2,
+        }
+    }
+}
+
+impl FromStr for StatusState {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        Ok(KNOWN
+            .iter()
+            .find(|state| state.as_str() == lower)
+            .cloned()
+            .unwrap_or(StatusState::Other(lower)))
+    }
+}
+
+impl fmt::Display for StatusState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for StatusState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(s.parse().unwrap_or_else(|never| match never {}))
+    }
+}
+
+/// A `--filter` value, either a state, a prefix ending in `*` or `none` for
+/// PRs without statuses
+#[derive(Debug, Clone)]
+pub struct StatusFilter {
+    pattern: String,
+    /// The known states the pattern matches
+    states: Vec<StatusState>,
+}
+
+impl StatusFilter {
+    pub fn matches(&self, state: &StatusState) -> bool {
+        self.states.contains(state)
+    }
+}
+
+impl fmt::Display for StatusFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+/// Parse a `--filter` value, values that can't match any state are
+/// rejected with the closest state name as a suggestion
+pub fn parse_filter(value: &str) -> Result<StatusFilter, String> {
+    let pattern = value.trim().to_lowercase();
+    let states: Vec<StatusState> = match pattern.strip_suffix('*') {
+        Some(prefix) => KNOWN.iter().filter(|s| s.as_str().starts_with(prefix)).cloned().collect(),
+        None if pattern == "none" => vec![StatusState::Missing],
+        None => KNOWN.iter().filter(|s| s.as_str() == pattern).cloned().collect(),
+    };
+    if states.is_empty() {
+        let mut names: Vec<&str> = KNOWN.iter().map(StatusState::as_str).collect();
+        names.push("none");
+        let closest = names
+            .iter()
+            .min_by_key(|name| edit_distance(name, pattern.trim_end_matches('*')))
+            .expect("known states");
+        return Err(format!(
+            "{} doesn't match any status, did you mean {}? (expected one of {})",
+            value,
+            closest,
+            names.join(", ")
+        ));
+    }
+    Ok(StatusFilter { pattern, states })
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            row.push(substitution.min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states_ignore_case() {
+        assert_eq!("SUCCESS".parse::<StatusState>(), Ok(StatusState::Success));
+        assert_eq!(" Failure ".parse::<StatusState>(), Ok(StatusState::Failure));
+        assert_eq!("Neutral".parse::<StatusState>(), Ok(StatusState::Other("neutral".to_string())));
+        let state: StatusState = serde_json::from_str(r#""PENDING""#).unwrap();
+        assert_eq!(state, StatusState::Pending);
+    }
+
+    #[test]
+    fn filters_ignore_case() {
+        let filter = parse_filter("Success").unwrap();
+        assert!(filter.matches(&"SUCCESS".parse().unwrap()));
+        assert!(filter.matches(&StatusState::Success));
+        assert!(!filter.matches(&StatusState::Failure));
+        assert_eq!(filter.to_string(), "success");
+    }
+
+    #[test]
+    fn trailing_wildcard() {
+        let filter = parse_filter("succ*").unwrap();
+        assert!(filter.matches(&StatusState::Success));
+        assert!(!filter.matches(&StatusState::Pending));
+        let all = parse_filter("*").unwrap();
+        assert!(KNOWN.iter().all(|state| all.matches(state)));
+        assert!(!all.matches(&StatusState::Missing));
+        let f = parse_filter("F*").unwrap();
+        assert!(f.matches(&StatusState::Failure));
+        assert!(!f.matches(&StatusState::Error));
+    }
+
+    #[test]
+    fn none_matches_missing() {
+        let filter = parse_filter("NONE").unwrap();
+        assert!(filter.matches(&StatusState::Missing));
+        assert!(!filter.matches(&StatusState::Success));
+    }
+
+    #[test]
+    fn suggests_the_closest_state() {
+        let err = parse_filter("sucess").unwrap_err();
+        assert!(err.contains("did you mean success?"), "{}", err);
+        assert!(err.contains("expected one of pending, success, failure, error, none"), "{}", err);
+        let err = parse_filter("fail").unwrap_err();
+        assert!(err.contains("did you mean failure?"), "{}", err);
+        let err = parse_filter("x*").unwrap_err();
+        assert!(err.starts_with("x* doesn't match any status"), "{}", err);
+    }
+}
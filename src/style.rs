@@ -0,0 +1,85 @@
+//! Coloring the human output
+//!
+//! Color is off when `NO_COLOR` is set, when stdout isn't a terminal or when
+//! stdout carries an --output document, unless `--color always` is passed.
+
+use crate::status::StatusState;
+use std::{
+    fmt::Display,
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+pub fn set_color(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                && std::io::stdout().is_terminal()
+                && !crate::output::is_machine()
+        }
+    };
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Color {
+    Green,
+    Red,
+    Yellow,
+    Dim,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Color::Green => "32",
+            Color::Red => "31",
+            Color::Yellow => "33",
+            Color::Dim => "2",
+        }
+    }
+}
+
+pub fn paint(text: impl Display, color: Color) -> String {
+    if ENABLED.load(Ordering::Relaxed) {
+        format!("\x1b[{}m{}\x1b[0m", color.code(), text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// A status colored by how good it is
+pub fn status(state: &StatusState) -> String {
+    match state {
+        StatusState::Success => paint(state, Color::Green),
+        StatusState::Failure | StatusState::Error => paint(state, Color::Red),
+        StatusState::Pending => paint(state, Color::Yellow),
+        StatusState::Other(_) | StatusState::Missing => state.to_string(),
+    }
+}
+
+/// The number of characters that show up on screen, skipping color codes
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if in_escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
@@ -0,0 +1,16 @@
+//! `-v` diagnostics on stderr, see the `verbose!` macro in main.rs
+//!
+//! One `-v` shows every request github answered and why each PR was left
+//! out, `-vv` adds retries, rate limits and the PRs that were kept.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static LEVEL: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_level(level: u8) {
+    LEVEL.store(level, Ordering::Relaxed);
+}
+
+pub fn level() -> u8 {
+    LEVEL.load(Ordering::Relaxed)
+}